@@ -1,14 +1,19 @@
 #![feature(allocator_api)]
 
 use std::cell::UnsafeCell;
+use std::collections::HashMap;
 use std::io::{Error, Result, IsTerminal, stderr};
-use std::os::unix::fs::{FileExt, FileTypeExt, OpenOptionsExt};
+use std::os::unix::fs::{FileExt, FileTypeExt, MetadataExt, OpenOptionsExt};
 use std::os::unix::io::AsRawFd;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc;
+use std::thread;
 use std::time::{Duration, Instant};
 
 use clap::Parser;
 use bytesize::ByteSize;
 use libc::{c_ushort, c_int, size_t};
+use nix::errno::Errno;
 use nix::{ioctl_read, ioctl_read_bad, ioctl_write_ptr, request_code_none};
 use sensitive::alloc::Sensitive;
 
@@ -37,16 +42,64 @@ struct Opt {
 
 	/// Enumerate corrupt sectors to standard output
 	#[arg(short, long)]
-	enumerate: bool
+	enumerate: bool,
+
+	/// Deallocate corrupt sectors instead of overwriting them with zeroes
+	#[arg(long)]
+	discard: bool,
+
+	/// Scan with this many concurrent worker threads, each with its own descriptor
+	#[arg(short, long, default_value_t = 1)]
+	jobs: usize,
+
+	/// Repeating byte pattern to fill corrupt sectors with, as hex (default: zero)
+	#[arg(long, value_parser = parse_pattern, default_value = "00")]
+	pattern: Pattern,
+
+	/// Re-read repaired sectors through the direct descriptor and confirm the pattern landed
+	#[arg(long)]
+	verify: bool,
+
+	/// Logical sector size (bytes) to use for a regular-file image, overriding its block size
+	#[arg(long)]
+	sector_size: Option<usize>
+}
+
+/// A `--pattern` value, wrapped so `clap` doesn't mistake a lone `Vec<u8>` for a multi-valued
+/// argument whose occurrences are collected one byte at a time.
+#[derive(Clone)]
+struct Pattern(Vec<u8>);
+
+impl std::ops::Deref for Pattern {
+	type Target = [u8];
+
+	fn deref(&self) -> &[u8] {
+		&self.0
+	}
+}
+
+fn parse_pattern(s: &str) -> std::result::Result<Pattern, String> {
+	let s = s.strip_prefix("0x").unwrap_or(s);
+
+	if s.is_empty() || !s.len().is_multiple_of(2) {
+		return Err("pattern must be a non-empty, even-length hex string".to_string());
+	}
+
+	(0..s.len()).step_by(2)
+		.map(|i| u8::from_str_radix(&s[i..i + 2], 16).map_err(|err| err.to_string()))
+		.collect::<std::result::Result<Vec<u8>, String>>()
+		.map(Pattern)
 }
 
 struct Device {
+	path: std::path::PathBuf,
 	direct: std::fs::File,
 	buffered: Option<std::fs::File>,
+	block_device: bool,
 	sectors: u64,
 	sector_size: usize,
 	maximum_io: u16,
-	null: Vec<u8>,
+	pattern: Vec<u8>,
 	buffer: UnsafeCell<Vec<u8, Sensitive>>
 }
 
@@ -62,20 +115,21 @@ struct ChunkIterator<'t> {
 	index: Option<u64>,
 }
 
-struct Sector<'t> {
+struct CorruptRun<'t> {
 	chunk: &'t Chunk<'t>,
-	index: u16,
-	valid: bool
+	start: u16,
+	len: u16
 }
 
 struct SectorIterator<'t> {
 	chunk: &'t Chunk<'t>,
-	index: Option<u16>
+	index: u16
 }
 
 struct Progress {
 	total: u64,
 	error: u64,
+	unrepaired: u64,
 	start: Instant,
 	last: Option<Instant>,
 	tty: bool
@@ -86,73 +140,97 @@ ioctl_read_bad!(blksszget, request_code_none!(0x12, 104), c_int);
 ioctl_read!(blkbszget, 0x12, 112, size_t);
 ioctl_write_ptr!(blkbszset, 0x12, 113, size_t);
 ioctl_read!(blkgetsize64, 0x12, 114, u64);
+ioctl_write_ptr!(blkdiscard, 0x12, 119, [u64; 2]);
+ioctl_write_ptr!(blkzeroout, 0x12, 127, [u64; 2]);
+
+/// Chunk size, in sectors, used for a regular-file image. Block devices report their own
+/// request-queue limit via `BLKSECTGET`; a plain file has no such limit to query, so pick a
+/// generous fixed size instead.
+const MAXIMUM_IO_REGULAR_FILE: u16 = 256;
 
 impl Device {
-	fn open<P: AsRef<std::path::Path>>(path: P, writable: bool, exclusive: bool) -> Result<Self> {
+	fn open<P: AsRef<std::path::Path>>(path: P, writable: bool, exclusive: bool, pattern: &[u8],
+	                                   sector_size: Option<usize>) -> Result<Self> {
+		let path = path.as_ref().to_path_buf();
 		let direct = std::fs::OpenOptions::new().read(true)
-			.custom_flags(libc::O_DIRECT | if exclusive { libc::O_EXCL } else { 0 }).open(path)?;
+			.custom_flags(libc::O_DIRECT | if exclusive { libc::O_EXCL } else { 0 }).open(&path)?;
 		let buffered = if writable {
 			Some(std::fs::OpenOptions::new().write(true).open(format!("/proc/self/fd/{}", direct.as_raw_fd()))?)
 		} else {
 			None
 		};
 
-		if !direct.metadata()?.file_type().is_block_device() {
-			use std::io::ErrorKind;
-			return Err(Error::new(ErrorKind::InvalidInput, "File is not a block device"));
-		}
+		let metadata = direct.metadata()?;
+		let block_device = metadata.file_type().is_block_device();
+
+		let (size, sector_size, maximum_io) = if block_device {
+			let size = {
+				let mut size = 0;
+				unsafe { blkgetsize64(direct.as_raw_fd(), &mut size) }?;
+				size
+			};
+
+			let sector_size = {
+				let mut ssz = 0;
+				unsafe { blksszget(direct.as_raw_fd(), &mut ssz) }?;
+				assert!(ssz > 0);
+				usize::try_from(ssz).unwrap()
+			};
+
+			fn block_size(file: &std::fs::File) -> Result<usize> {
+				let mut bsz = 0;
+				unsafe { blkbszget(file.as_raw_fd(), &mut bsz) }?;
+				assert!(bsz > 0);
+				Ok(bsz)
+			}
 
-		let size = {
-			let mut size = 0;
-			unsafe { blkgetsize64(direct.as_raw_fd(), &mut size) }?;
-			size
-		};
+			if block_size(&direct)? != sector_size {
+				unsafe { blkbszset(direct.as_raw_fd(), &sector_size) }?;
+			}
 
-		let sector_size = {
-			let mut ssz = 0;
-			unsafe { blksszget(direct.as_raw_fd(), &mut ssz) }?;
-			assert!(ssz > 0);
-			usize::try_from(ssz).unwrap()
-		};
+			// Assert that block size change affects buffered descriptor
+			if let Some(ref file) = buffered {
+				assert_eq!(block_size(file)?, sector_size);
+			}
 
-		// Assert that device size is a multiple of the logical sector size
-		assert!(size % sector_size as u64 == 0);
+			let maximum_io = {
+				let mut sect = 0;
+				unsafe { blksectget(direct.as_raw_fd(), &mut sect) }?;
+				assert!(sect > 0);
+				sect
+			};
 
-		fn block_size(file: &std::fs::File) -> Result<usize> {
-			let mut bsz = 0;
-			unsafe { blkbszget(file.as_raw_fd(), &mut bsz) }?;
-			assert!(bsz > 0);
-			Ok(bsz)
-		}
+			(size, sector_size, maximum_io)
+		} else if metadata.file_type().is_file() {
+			let sector_size = sector_size.unwrap_or_else(|| usize::try_from(metadata.blksize()).unwrap());
+			assert!(sector_size > 0);
 
-		if block_size(&direct)? != sector_size {
-			unsafe { blkbszset(direct.as_raw_fd(), &sector_size) }?;
-		}
+			(metadata.len(), sector_size, MAXIMUM_IO_REGULAR_FILE)
+		} else {
+			use std::io::ErrorKind;
+			return Err(Error::new(ErrorKind::InvalidInput, "File is neither a block device nor a regular file"));
+		};
 
-		// Assert that block size change affects buffered descriptor
-		if let Some(ref file) = buffered {
-			assert_eq!(block_size(file)?, sector_size);
+		if size % sector_size as u64 != 0 {
+			use std::io::ErrorKind;
+			return Err(Error::new(ErrorKind::InvalidInput,
+			                       format!("Device size {size} is not a multiple of the {sector_size}-byte sector size")));
 		}
 
-		let maximum_io = {
-			let mut sect = 0;
-			unsafe { blksectget(direct.as_raw_fd(), &mut sect) }?;
-			assert!(sect > 0);
-			sect
-		};
-
 		let mut buffer = Vec::with_capacity_in(maximum_io as usize * sector_size, Sensitive);
 
 		// The allocator ensures that the memory is zero‐initialised
 		unsafe { buffer.set_len(maximum_io as usize * sector_size); }
 
 		Ok(Self {
+			path,
 			direct,
 			buffered,
+			block_device,
 			sectors: size / sector_size as u64,
 			sector_size,
 			maximum_io,
-			null: vec![0; sector_size],
+			pattern: pattern.iter().copied().cycle().take(sector_size).collect(),
 			buffer: UnsafeCell::new(buffer)
 		})
 	}
@@ -163,12 +241,21 @@ impl Device {
 		// The contents of this buffer are never examined
 		let buffer = unsafe { &mut *self.buffer.get() };
 
-		match self.direct.read_at(&mut buffer[..count as usize * self.sector_size], offset * self.sector_size as u64) {
+		Self::read(&self.direct, buffer, self.sector_size, offset, count)
+	}
+
+	/// Read `count` sectors at `offset` through `file` into `buffer`, reporting an `EILSEQ`
+	/// failure as `Ok(None)` the same way [`Device::test`] does.
+	///
+	/// Shared between `test` and the pipelined worker threads so each worker can drive its own
+	/// descriptor and buffer through the same short-read/corruption semantics.
+	fn read(file: &std::fs::File, buffer: &mut [u8], sector_size: usize, offset: u64, count: u16) -> Result<Option<u16>> {
+		match file.read_at(&mut buffer[..count as usize * sector_size], offset * sector_size as u64) {
 			Ok(len) => {
 				// Assert that we read a multiple of the sector size
-				assert!(len % self.sector_size == 0);
+				assert!(len % sector_size == 0);
 
-				Ok(Some(u16::try_from(len / self.sector_size).unwrap()))
+				Ok(Some(u16::try_from(len / sector_size).unwrap()))
 			}
 
 			Err(err) => {
@@ -181,8 +268,67 @@ impl Device {
 		}
 	}
 
-	fn zero(&self, offset: u64) -> Result<()> {
-		self.buffered.as_ref().unwrap().write_all_at(&self.null, offset * self.sector_size as u64)
+	/// Open an independent read-only `O_DIRECT` descriptor onto `path`, for use by a pipelined
+	/// scanning worker. Takes the path rather than `&self` so it can be called from a worker
+	/// thread without requiring `Device` itself to be `Sync`.
+	fn open_reader<P: AsRef<std::path::Path>>(path: P) -> Result<std::fs::File> {
+		std::fs::OpenOptions::new().read(true).custom_flags(libc::O_DIRECT).open(path)
+	}
+
+	/// Repair a contiguous run of `len` corrupt sectors starting at `offset`.
+	///
+	/// Prefers `BLKDISCARD` (when `discard` is set) or `BLKZEROOUT`, which let the device
+	/// deallocate or hardware-zero the range in a single operation. An explicit non-zero
+	/// `pattern` without `discard`, a kernel that reports `EOPNOTSUPP` for the ioctl, or a
+	/// regular-file image (neither ioctl applies to one) all fall back to the buffered path below.
+	///
+	/// Returns whether the range was actually discarded via `BLKDISCARD`, as opposed to filled
+	/// with a deterministic pattern (`BLKZEROOUT`, the buffered fallback): a discarded range's
+	/// contents are left up to the device, so [`Device::verify`] must not compare them against
+	/// `pattern`.
+	fn repair(&self, offset: u64, len: u64, discard: bool) -> Result<bool> {
+		let range = [offset * self.sector_size as u64, len * self.sector_size as u64];
+		let zero = self.pattern.iter().all(|&byte| byte == 0);
+
+		if self.block_device && (discard || zero) {
+			let fd = self.buffered.as_ref().unwrap().as_raw_fd();
+
+			let result = if discard {
+				unsafe { blkdiscard(fd, &range) }
+			} else {
+				unsafe { blkzeroout(fd, &range) }
+			};
+
+			match result {
+				Ok(_) => return Ok(discard),
+				Err(Errno::EOPNOTSUPP) => eprintln!("warning: {} not supported, falling back to buffered write",
+				                                     if discard { "BLKDISCARD" } else { "BLKZEROOUT" }),
+				Err(err) => return Err(err.into())
+			}
+		} else if discard {
+			eprintln!("warning: BLKDISCARD not supported on a regular-file image, falling back to buffered write");
+		}
+
+		for sector in offset..offset + len {
+			self.buffered.as_ref().unwrap().write_all_at(&self.pattern, sector * self.sector_size as u64)?;
+		}
+
+		Ok(false)
+	}
+
+	/// Re-read `len` sectors at `offset` through the `O_DIRECT` descriptor and confirm the
+	/// repair landed: every sector must read back without error, and, unless the range was
+	/// actually discarded rather than pattern-filled, must match `pattern` exactly.
+	fn verify(&self, offset: u64, len: u64, discarded: bool) -> Result<bool> {
+		let buffer = unsafe { &mut *self.buffer.get() };
+		let count = u16::try_from(len).unwrap();
+
+		match Self::read(&self.direct, buffer, self.sector_size, offset, count)? {
+			Some(n) if n == count => Ok(discarded || buffer[..len as usize * self.sector_size]
+				.chunks_exact(self.sector_size)
+				.all(|sector| sector == self.pattern)),
+			_ => Ok(false)
+		}
 	}
 
 	fn flush(&self, offset: u64, count: u16) -> Result<()> {
@@ -225,7 +371,7 @@ impl Chunk<'_> {
 	fn iter(&self) -> SectorIterator<'_> {
 		SectorIterator {
 			chunk: self,
-			index: None
+			index: 0
 		}
 	}
 
@@ -276,67 +422,83 @@ impl<'t> Iterator for ChunkIterator<'t> {
 	}
 }
 
-impl Sector<'_> {
+impl CorruptRun<'_> {
 	fn absolute(&self) -> u64 {
-		self.chunk.index + u64::from(self.index)
+		self.chunk.index + u64::from(self.start)
 	}
 
-	fn zero(&self) -> Result<()> {
-		self.chunk.device.zero(self.absolute())
-	}
-}
-
-impl SectorIterator<'_> {
-	fn absolute(&self) -> u64 {
-		self.chunk.index + u64::from(self.index.unwrap_or(0))
+	// SectorIterator::next never yields a zero-length run, so an is_empty() would be dead code.
+	#[allow(clippy::len_without_is_empty)]
+	fn len(&self) -> u64 {
+		u64::from(self.len)
 	}
 }
 
 impl<'t> Iterator for SectorIterator<'t> {
-	type Item = Result<Sector<'t>>;
-
+	type Item = Result<CorruptRun<'t>>;
+
+	// A corrupt chunk always begins on a bad sector, so `self.index` is known bad on entry:
+	// either it is the chunk's leading corrupt sector, or it is the sector immediately
+	// following a good run located by the previous call.
+	//
+	// `Device::test` only ever reports the extent of a GOOD run: a probe starting on a bad
+	// sector fails outright and says nothing about how far the corruption reaches, no matter
+	// how wide a range is requested. That means nothing short of reading every sector in the
+	// leading corrupt run can be certain where it ends -- a chunk can hold several disjoint
+	// corrupt runs separated by good ones, so any attempt to bisect the remainder looking for
+	// "the" good/bad boundary can step clean over a short good run sitting between two corrupt
+	// ones, silently coalescing it into a reported (and then repaired) corrupt range. So this
+	// deliberately probes sector by sector until the leading corrupt run's actual end is
+	// confirmed -- there is no sub-linear way to do this safely -- then issues a single wide
+	// read from that good sector to learn how far the good run extends, so the next call can
+	// resume at the following bad sector without having probed it sector by sector.
 	fn next(&mut self) -> Option<Self::Item> {
-		if let Some(index) = self.index
-			&& index >= self.chunk.count {
-				return None;
-			}
+		if self.index >= self.chunk.count {
+			return None;
+		}
 
-		match self.chunk.device.test(self.absolute(), 1) {
-			Ok(Some(0)) => None,
-			Ok(Some(len)) => {
-				assert_eq!(len, 1);
-				let sector = Sector {
-					chunk: self.chunk,
-					index: self.index.unwrap_or(0),
-					valid: true
-				};
+		let start = self.index;
 
-				self.index = Some(self.index.unwrap_or(0) + len);
+		while self.index < self.chunk.count {
+			match self.chunk.device.test(self.chunk.index + u64::from(self.index), 1) {
+				Ok(Some(_)) => break,
+				Ok(None) => self.index += 1,
+				Err(err) => return Some(Err(err))
+			}
+		}
 
-				Some(Ok(sector))
-			},
-			Ok(None) => {
-				let sector = Sector {
-					chunk: self.chunk,
-					index: self.index.unwrap_or(0),
-					valid: false
-				};
+		let run = CorruptRun {
+			chunk: self.chunk,
+			start,
+			len: self.index - start
+		};
 
-				self.index = Some(self.index.unwrap_or(0) + 1);
+		if self.index == self.chunk.count {
+			return Some(Ok(run));
+		}
 
-				Some(Ok(sector))
-			},
-			Err(err) => Some(Err(err))
+		match self.chunk.device.test(self.chunk.index + u64::from(self.index), self.chunk.count - self.index) {
+			Ok(Some(len)) if len > 0 => {
+				self.index += len;
+
+				// The good run reached the device's end; there is nothing left to scan.
+				if self.chunk.index + u64::from(self.index) >= self.chunk.device.sectors {
+					self.index = self.chunk.count;
+				}
+			}
+			// The device ended inside the good run; there is nothing left to scan.
+			Ok(Some(_)) => self.index = self.chunk.count,
+			Ok(None) => unreachable!("the probe loop above confirmed sector {} is good", self.index),
+			Err(err) => return Some(Err(err))
 		}
+
+		Some(Ok(run))
 	}
 
 	fn size_hint(&self) -> (usize, Option<usize>) {
-		let rem = std::cmp::min(
-			u64::from(self.chunk.count.saturating_sub(self.index.unwrap_or(0))),
-			self.chunk.device.sectors.saturating_sub(self.absolute())
-		);
+		let rem = self.chunk.count.saturating_sub(self.index);
 		#[allow(clippy::cast_possible_truncation)]
-		(rem as usize, rem.try_into().ok())
+		(usize::from(rem > 0), Some(rem as usize))
 	}
 }
 
@@ -345,6 +507,7 @@ impl Progress {
 		Ok(Self {
 			total: 0,
 			error: 0,
+			unrepaired: 0,
 			start: Instant::now(),
 			last: None,
 			tty: stderr().is_terminal()
@@ -360,12 +523,13 @@ impl Progress {
 			eprint!("\x1bM\x1b[K");
 		}
 
-		eprintln!("{:>3} %   {:>9} / {}   {:>9} / s   {} corrupt sectors",
+		eprintln!("{:>3} %   {:>9} / {}   {:>9} / s   {} corrupt sectors   {} unrepaired",
 		          self.total * 100 / dev.sectors,
 		          ByteSize::b(self.total * dev.sector_size as u64),
 		          ByteSize::b(dev.sectors * dev.sector_size as u64),
 		          Self::rate(self.total * dev.sector_size as u64, now.duration_since(self.start)),
-		          self.error);
+		          self.error,
+		          self.unrepaired);
 		self.last = Some(now);
 	}
 
@@ -386,51 +550,199 @@ impl Progress {
 	}
 }
 
-fn main() -> Result<()> {
-	let opt = Opt::parse();
+/// Enumerate, repair and account for a single chunk already known to be valid or invalid.
+///
+/// Shared between the serial and pipelined scanners, which differ only in how they produce
+/// `Chunk`s in offset order; the per-chunk repair and progress accounting is identical.
+fn process_chunk(dev: &Device, opt: &Opt, prog: &mut Progress, chunk: &Chunk) -> Result<()> {
+	if !chunk.valid {
+		let mut repaired = Vec::new();
 
-	if opt.idle {
-		use ioprio::{set_priority, Target, Pid, Priority, Class};
-		set_priority(Target::Process(Pid::this()), Priority::new(Class::Idle)).unwrap();
-	}
+		for run in chunk.iter() {
+			let run = run?;
 
-	let dev = Device::open(&opt.device, !opt.dry_run, !opt.force)?;
+			prog.error += run.len();
 
-	let mut prog = Progress::new()?;
+			if opt.enumerate {
+				println!("{}+{}", run.absolute(), run.len());
+			}
 
-	if !opt.quiet {
-		eprintln!();
-		prog.print_now(&dev);
+			if !opt.dry_run {
+				let discarded = dev.repair(run.absolute(), run.len(), opt.discard)?;
+				repaired.push((run.absolute(), run.len(), discarded));
+			}
+		}
+
+		chunk.flush()?;
+
+		if opt.verify {
+			for (offset, len, discarded) in repaired {
+				if !dev.verify(offset, len, discarded)? {
+					prog.unrepaired += len;
+				}
+			}
+		}
 	}
 
+	prog.total += u64::from(chunk.count);
+
+	Ok(())
+}
+
+fn scan_serial(dev: &Device, opt: &Opt, prog: &mut Progress) -> Result<()> {
 	for chunk in dev.iter() {
 		let chunk = chunk?;
 
 		if !opt.quiet {
-			prog.print_50(&dev);
+			prog.print_50(dev);
 		}
 
-		if !chunk.valid {
-			for sector in chunk.iter() {
-				let sector = sector?;
+		process_chunk(dev, opt, prog, &chunk)?;
+	}
+
+	Ok(())
+}
+
+/// Scan with `opt.jobs` worker threads, each holding its own `O_DIRECT` descriptor and buffer,
+/// pulling chunk indices from a shared cursor and feeding their `test()` results back through a
+/// bounded channel. The main thread is the sole consumer: it reorders completions (workers may
+/// finish out of order) and replays them through [`process_chunk`] in ascending offset order, so
+/// progress accounting, `--enumerate` output and repairs cover the same sectors in the same order
+/// as a serial scan. Chunks start at fixed offsets here, so a corrupt run that crosses a chunk
+/// boundary can be reported as two runs split at a different sector than the serial scan splits it.
+/// Repairs themselves stay on the main thread, serialized through `dev`'s single buffered
+/// descriptor.
+fn scan_pipelined(dev: &Device, opt: &Opt, prog: &mut Progress) -> Result<()> {
+	let jobs = opt.jobs;
+	let chunks = dev.chunks();
+	let sector_size = dev.sector_size;
+	let maximum_io = dev.maximum_io;
+	let cursor = AtomicU64::new(0);
+	let (tx, rx) = mpsc::sync_channel::<Result<(u64, u16, bool)>>(jobs * 2);
+
+	thread::scope(|scope| -> Result<()> {
+		for _ in 0..jobs {
+			let tx = tx.clone();
+			let cursor = &cursor;
+			let path = dev.path.clone();
+
+			scope.spawn(move || {
+				let file = match Device::open_reader(&path) {
+					Ok(file) => file,
+					Err(err) => {
+						let _ = tx.send(Err(err));
+						return;
+					}
+				};
+
+				let mut buffer = Vec::with_capacity_in(maximum_io as usize * sector_size, Sensitive);
 
-				if !sector.valid {
-					prog.error += 1;
+				// The allocator ensures that the memory is zero‐initialised
+				unsafe { buffer.set_len(maximum_io as usize * sector_size); }
 
-					if opt.enumerate {
-						println!("{}", sector.absolute());
+				loop {
+					let index = cursor.fetch_add(1, Ordering::Relaxed);
+
+					if index >= chunks {
+						break;
 					}
 
-					if !opt.dry_run {
-						sector.zero()?;
+					let offset = index * u64::from(maximum_io);
+					let result = Device::read(&file, &mut buffer, sector_size, offset, maximum_io);
+
+					let sent = match result {
+						Ok(Some(count)) => tx.send(Ok((index, count, true))),
+						Ok(None) => tx.send(Ok((index, maximum_io, false))),
+						Err(err) => tx.send(Err(err))
+					};
+
+					if sent.is_err() {
+						break;
 					}
 				}
-			}
+			});
+		}
+
+		// Drop the template sender so the channel closes once every worker has finished.
+		drop(tx);
+
+		let mut pending = HashMap::new();
+		let mut next = 0;
+		let mut result = Ok(());
+
+		'outer: while next < chunks {
+			let (index, count, valid) = match rx.recv() {
+				Ok(Ok(result)) => result,
+				Ok(Err(err)) => { result = Err(err); break 'outer; }
+				Err(_) => break 'outer
+			};
+
+			pending.insert(index, (count, valid));
+
+			while let Some((count, valid)) = pending.remove(&next) {
+				let chunk = Chunk {
+					device: dev,
+					index: next * u64::from(dev.maximum_io),
+					count,
+					valid
+				};
+
+				if !opt.quiet {
+					prog.print_50(dev);
+				}
+
+				// A short good read stopped at a corrupt sector, where a serial scan would start its
+				// next chunk; scan the rest of this chunk from there as a corrupt chunk.
+				let good_end = chunk.index + u64::from(count);
+				let end = dev.sectors.min(chunk.index + u64::from(dev.maximum_io));
+				let rest = (valid && good_end < end).then(|| Chunk {
+					device: dev,
+					index: good_end,
+					count: u16::try_from(end - good_end).unwrap(),
+					valid: false
+				});
 
-			chunk.flush()?;
+				for chunk in std::iter::once(&chunk).chain(rest.as_ref()) {
+					if let Err(err) = process_chunk(dev, opt, prog, chunk) {
+						result = Err(err);
+						break 'outer;
+					}
+				}
+
+				next += 1;
+			}
 		}
 
-		prog.total += u64::from(chunk.count);
+		// Drop the receiver before returning so that, if we're bailing out early, any workers
+		// still blocked sending on the now-full bounded channel see it disconnect and exit,
+		// instead of hanging forever against `thread::scope`'s implicit join of those threads.
+		drop(rx);
+
+		result
+	})
+}
+
+fn main() -> Result<()> {
+	let opt = Opt::parse();
+
+	if opt.idle {
+		use ioprio::{set_priority, Target, Pid, Priority, Class};
+		set_priority(Target::Process(Pid::this()), Priority::new(Class::Idle)).unwrap();
+	}
+
+	let dev = Device::open(&opt.device, !opt.dry_run, !opt.force, &opt.pattern, opt.sector_size)?;
+
+	let mut prog = Progress::new()?;
+
+	if !opt.quiet {
+		eprintln!();
+		prog.print_now(&dev);
+	}
+
+	if opt.jobs > 1 {
+		scan_pipelined(&dev, &opt, &mut prog)?;
+	} else {
+		scan_serial(&dev, &opt, &mut prog)?;
 	}
 
 	if !opt.quiet {